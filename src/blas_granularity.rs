@@ -0,0 +1,227 @@
+use std::{env::VarError, error::Error, time::Duration};
+
+// bricks per side of a brick group
+const GROUP_SIZE: u32 = 4;
+const GROUP_BRICKS: u32 = GROUP_SIZE * GROUP_SIZE * GROUP_SIZE;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlasGranularity {
+    // one aabb per occupied brick, tightest bounds but most primitives
+    Brick,
+    // one aabb per occupied 4³ group of bricks
+    Group,
+    // a single aabb traversed procedurally in the intersection shader
+    Chunk,
+}
+
+impl BlasGranularity {
+    pub fn parse(name: &str) -> Result<Self, Box<dyn Error>> {
+        match name {
+            "brick" => Ok(Self::Brick),
+            "group" => Ok(Self::Group),
+            "chunk" => Ok(Self::Chunk),
+            _ => Err(format!("Unknown BLAS granularity: {name}").into()),
+        }
+    }
+    pub fn primitive_count(self, stats: &OccupancyStats) -> u32 {
+        match self {
+            Self::Brick => stats.occupied_bricks,
+            Self::Group => stats.occupied_groups,
+            Self::Chunk => (stats.occupied_bricks > 0) as u32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OccupancyStats {
+    pub occupied_bricks: u32,
+    pub occupied_groups: u32,
+    pub total_bricks: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GranularityHeuristic {
+    // below this many bricks per brick aabbs are always cheap enough
+    pub max_brick_aabbs: u32,
+    // fraction of occupied bricks above which the whole chunk is one volume
+    pub dense_fill: f32,
+    // how full occupied groups must be on average for group aabbs to pay off
+    pub group_fill: f32,
+    // fraction each threshold moves in favour of the current mode, so chunks
+    // edited near a threshold don't flip (and fully rebuild) every frame
+    pub hysteresis: f32,
+    // skips the heuristic entirely, used when benchmarking each mode
+    pub forced: Option<BlasGranularity>,
+}
+
+impl Default for GranularityHeuristic {
+    fn default() -> Self {
+        Self {
+            max_brick_aabbs: 64,
+            dense_fill: 0.5,
+            group_fill: 0.25,
+            hysteresis: 0.1,
+            forced: None,
+        }
+    }
+}
+
+impl GranularityHeuristic {
+    // VOXEL_BLAS_GRANULARITY=brick|group|chunk pins every chunk to one mode so
+    // build time, memory and trace speed can be compared offline
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        let forced = match std::env::var("VOXEL_BLAS_GRANULARITY") {
+            Ok(name) => Some(BlasGranularity::parse(&name)?),
+            Err(VarError::NotPresent) => None,
+            Err(e) => return Err(format!("Invalid VOXEL_BLAS_GRANULARITY: {e}").into()),
+        };
+        Ok(Self {
+            forced,
+            ..Default::default()
+        })
+    }
+    // lowers the threshold when the current mode is the one picked above it
+    // and raises it when the current mode is the one picked below it
+    fn biased(&self, threshold: f32, staying_above: bool, staying_below: bool) -> f32 {
+        if staying_above {
+            threshold * (1.0 - self.hysteresis)
+        } else if staying_below {
+            threshold * (1.0 + self.hysteresis)
+        } else {
+            threshold
+        }
+    }
+    // current is the granularity the chunk was last built with, a change in
+    // the result means the chunk needs a full rebuild not a refit
+    pub fn choose(
+        &self,
+        current: Option<BlasGranularity>,
+        stats: &OccupancyStats,
+    ) -> BlasGranularity {
+        use BlasGranularity::*;
+
+        if let Some(forced) = self.forced {
+            return forced;
+        }
+        let is = |mode| current == Some(mode);
+
+        let brick_limit = self.biased(
+            self.max_brick_aabbs as f32,
+            is(Group) || is(Chunk),
+            is(Brick),
+        );
+        if stats.occupied_bricks as f32 <= brick_limit || stats.occupied_groups == 0 {
+            return Brick;
+        }
+
+        let fill = stats.occupied_bricks as f32 / stats.total_bricks.max(1) as f32;
+        if fill >= self.biased(self.dense_fill, is(Chunk), is(Group) || is(Brick)) {
+            return Chunk;
+        }
+
+        let group_fill =
+            stats.occupied_bricks as f32 / (stats.occupied_groups * GROUP_BRICKS) as f32;
+        if group_fill >= self.biased(self.group_fill, is(Group), is(Brick)) {
+            Group
+        } else {
+            Brick
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ModeTotals {
+    pub builds: u32,
+    pub build_time: Duration,
+    pub memory: u64,
+    pub primitives: u64,
+}
+
+// accumulates what each mode costs so runs pinned with VOXEL_BLAS_GRANULARITY
+// can be compared, trace speed is left to the frame profiler
+#[derive(Default)]
+pub struct GranularityBenchmark {
+    totals: [ModeTotals; 3],
+}
+
+impl GranularityBenchmark {
+    // build_time should be gpu time and memory the acceleration structure size
+    pub fn record(
+        &mut self,
+        granularity: BlasGranularity,
+        build_time: Duration,
+        memory: u64,
+        primitives: u32,
+    ) {
+        let totals = &mut self.totals[granularity as usize];
+        totals.builds += 1;
+        totals.build_time += build_time;
+        totals.memory += memory;
+        totals.primitives += primitives as u64;
+    }
+    pub fn totals(&self, granularity: BlasGranularity) -> ModeTotals {
+        self.totals[granularity as usize]
+    }
+    pub fn log_summary(&self) {
+        for mode in [
+            BlasGranularity::Brick,
+            BlasGranularity::Group,
+            BlasGranularity::Chunk,
+        ] {
+            let t = self.totals(mode);
+            if t.builds == 0 {
+                continue;
+            }
+            log::info!(
+                "{mode:?}: {} builds, {:?} avg build, {} avg bytes, {} avg primitives",
+                t.builds,
+                t.build_time / t.builds,
+                t.memory / t.builds as u64,
+                t.primitives / t.builds as u64
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BlasGranularity::*;
+
+    fn stats(occupied_bricks: u32, occupied_groups: u32, total_bricks: u32) -> OccupancyStats {
+        OccupancyStats {
+            occupied_bricks,
+            occupied_groups,
+            total_bricks,
+        }
+    }
+
+    // each case sits just past a threshold in the direction away from
+    // current, a fresh chunk flips but the built one keeps its mode
+    fn check(stats: OccupancyStats, current: BlasGranularity, fresh: BlasGranularity) {
+        let heuristic = GranularityHeuristic::default();
+        assert_eq!(heuristic.choose(None, &stats), fresh);
+        assert_eq!(heuristic.choose(Some(current), &stats), current);
+    }
+
+    #[test]
+    fn brick_count_hysteresis() {
+        // limit is 64 bricks
+        check(stats(68, 2, 4096), Brick, Group);
+        check(stats(60, 2, 4096), Group, Brick);
+    }
+
+    #[test]
+    fn dense_fill_hysteresis() {
+        // threshold is 50% of all bricks
+        check(stats(520, 16, 1000), Group, Chunk);
+        check(stats(480, 16, 1000), Chunk, Group);
+    }
+
+    #[test]
+    fn group_fill_hysteresis() {
+        // threshold is 25% of the bricks in occupied groups
+        check(stats(170, 10, 4096), Brick, Group);
+        check(stats(150, 10, 4096), Group, Brick);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, time::Duration};
+
+pub type ChunkPos = [i32; 3];
+
+const CHUNK_SIZE: f32 = 32.0;
+// refitting degrades bvh quality so force a full rebuild every so often
+const MAX_REFITS_BEFORE_REBUILD: u32 = 8;
+// chunks dirty for this many frames are built before any others, oldest first
+const STARVATION_FRAMES: u64 = 30;
+// weight of the newest sample in the moving average of build costs
+const COST_SMOOTHING: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildKind {
+    // primitive count unchanged, only the aabbs moved
+    Refit,
+    // first build or primitives were added or removed
+    Rebuild,
+}
+
+struct DirtyChunk {
+    primitives: u32,
+    force_rebuild: bool,
+    dirty_since: u64,
+}
+
+// state of the blas currently on the gpu, vulkan can only refit a blas that
+// was built before (with ALLOW_UPDATE) and has the same primitive count
+struct BuiltChunk {
+    primitives: u32,
+    refits: u32,
+}
+
+pub struct BuildScheduler {
+    dirty: HashMap<ChunkPos, DirtyChunk>,
+    built: HashMap<ChunkPos, BuiltChunk>,
+    budget: Duration,
+    frame: u64,
+    // estimated cost per primitive in seconds, build time is roughly linear
+    // in primitive count so this holds across chunk sizes
+    refit_cost: f64,
+    rebuild_cost: f64,
+}
+
+impl BuildScheduler {
+    pub fn new(budget_ms: f32) -> Self {
+        Self {
+            dirty: HashMap::new(),
+            built: HashMap::new(),
+            budget: budget_from_ms(budget_ms),
+            frame: 0,
+            // pessimistic guesses until real timings are reported
+            refit_cost: 2e-7,
+            rebuild_cost: 1e-6,
+        }
+    }
+    pub fn set_budget(&mut self, budget_ms: f32) {
+        self.budget = budget_from_ms(budget_ms);
+    }
+    pub fn pending(&self) -> usize {
+        self.dirty.len()
+    }
+    // primitives is the count the next build will have, force_rebuild is for
+    // changes a refit can't express with the same count such as switching
+    // BlasGranularity
+    pub fn mark_dirty(&mut self, pos: ChunkPos, primitives: u32, force_rebuild: bool) {
+        let frame = self.frame;
+        let chunk = self.dirty.entry(pos).or_insert(DirtyChunk {
+            primitives,
+            force_rebuild,
+            dirty_since: frame,
+        });
+        chunk.primitives = primitives;
+        chunk.force_rebuild |= force_rebuild;
+    }
+    // chunk was unloaded so any pending build is pointless
+    pub fn remove(&mut self, pos: ChunkPos) {
+        self.dirty.remove(&pos);
+        self.built.remove(&pos);
+    }
+    fn kind(&self, pos: &ChunkPos, chunk: &DirtyChunk) -> BuildKind {
+        match self.built.get(pos) {
+            Some(built)
+                if !chunk.force_rebuild
+                    && built.primitives == chunk.primitives
+                    && built.refits < MAX_REFITS_BEFORE_REBUILD =>
+            {
+                BuildKind::Refit
+            }
+            _ => BuildKind::Rebuild,
+        }
+    }
+    fn cost(&self, kind: BuildKind, primitives: u32) -> f64 {
+        let per_primitive = match kind {
+            BuildKind::Refit => self.refit_cost,
+            BuildKind::Rebuild => self.rebuild_cost,
+        };
+        per_primitive * primitives.max(1) as f64
+    }
+    fn priority(pos: &ChunkPos, camera: [f32; 3], dir: [f32; 3]) -> f32 {
+        let to_chunk = [0, 1, 2].map(|i| (pos[i] as f32 + 0.5) * CHUNK_SIZE - camera[i]);
+        let dist = to_chunk.iter().map(|v| v * v).sum::<f32>().sqrt().max(1.0);
+
+        // approximate projected size, chunks behind the camera still matter
+        // for shadows and reflections but much less
+        let facing = (0..3).map(|i| to_chunk[i] * dir[i]).sum::<f32>() / dist;
+        let facing = if facing > 0.0 { 1.0 } else { 0.25 };
+        facing * CHUNK_SIZE / dist
+    }
+    // picks the chunks to build this frame, dir is expected to be normalised
+    pub fn schedule(&mut self, camera: [f32; 3], dir: [f32; 3]) -> Vec<(ChunkPos, BuildKind)> {
+        self.frame += 1;
+
+        let frame = self.frame;
+        let mut queue: Vec<_> = self
+            .dirty
+            .iter()
+            .map(|(pos, chunk)| {
+                let starved = frame - chunk.dirty_since >= STARVATION_FRAMES;
+                let priority = Self::priority(pos, camera, dir);
+                let kind = self.kind(pos, chunk);
+                (
+                    starved,
+                    chunk.dirty_since,
+                    priority,
+                    *pos,
+                    kind,
+                    chunk.primitives,
+                )
+            })
+            .collect();
+        queue.sort_unstable_by(|a, b| match (a.0, b.0) {
+            (true, true) => a.1.cmp(&b.1),
+            (false, false) => b.2.total_cmp(&a.2),
+            _ => b.0.cmp(&a.0),
+        });
+
+        let budget = self.budget.as_secs_f64();
+        let mut spent = 0.0;
+        let mut selected = Vec::new();
+        for (_, _, _, pos, kind, primitives) in queue {
+            let cost = self.cost(kind, primitives);
+            // always make progress even if a single build blows the budget
+            if !selected.is_empty() && spent + cost > budget {
+                continue;
+            }
+            spent += cost;
+            selected.push((pos, kind));
+        }
+
+        for (pos, kind) in &selected {
+            let chunk = self.dirty.remove(pos).unwrap();
+            let built = self.built.entry(*pos).or_insert(BuiltChunk {
+                primitives: chunk.primitives,
+                refits: 0,
+            });
+            match kind {
+                BuildKind::Refit => built.refits += 1,
+                BuildKind::Rebuild => {
+                    built.primitives = chunk.primitives;
+                    built.refits = 0;
+                }
+            }
+        }
+
+        selected
+    }
+    // feed back measured build times so the budget estimate stays honest,
+    // this should be gpu time from timestamp queries as recording the
+    // commands on the cpu is nearly free
+    pub fn report(&mut self, kind: BuildKind, primitives: u32, elapsed: Duration) {
+        let sample = elapsed.as_secs_f64() / primitives.max(1) as f64;
+        let cost = match kind {
+            BuildKind::Refit => &mut self.refit_cost,
+            BuildKind::Rebuild => &mut self.rebuild_cost,
+        };
+        *cost += (sample - *cost) * COST_SMOOTHING;
+    }
+}
+
+// negative & nan budgets become zero (one build per frame), infinite ones unlimited
+fn budget_from_ms(budget_ms: f32) -> Duration {
+    Duration::try_from_secs_f32(budget_ms.max(0.0) / 1000.0).unwrap_or(Duration::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: [f32; 3] = [0.0; 3];
+    const DIR: [f32; 3] = [0.0, 0.0, 1.0];
+
+    #[test]
+    fn first_build_is_rebuild() {
+        let mut scheduler = BuildScheduler::new(f32::INFINITY);
+        scheduler.mark_dirty([0, 0, 0], 10, false);
+        assert_eq!(
+            scheduler.schedule(CAMERA, DIR),
+            [([0, 0, 0], BuildKind::Rebuild)]
+        );
+
+        scheduler.mark_dirty([0, 0, 0], 10, false);
+        assert_eq!(
+            scheduler.schedule(CAMERA, DIR),
+            [([0, 0, 0], BuildKind::Refit)]
+        );
+
+        scheduler.mark_dirty([0, 0, 0], 11, false);
+        assert_eq!(
+            scheduler.schedule(CAMERA, DIR),
+            [([0, 0, 0], BuildKind::Rebuild)]
+        );
+    }
+
+    #[test]
+    fn budget_scales_with_primitives() {
+        // measured at 0.1µs per primitive, so a 1ms budget fits 10k primitives
+        let schedule = |primitives| {
+            let mut scheduler = BuildScheduler::new(1.0);
+            for _ in 0..200 {
+                scheduler.report(BuildKind::Rebuild, 1000, Duration::from_micros(100));
+            }
+            for z in 0..4 {
+                scheduler.mark_dirty([0, 0, z], primitives, false);
+            }
+            scheduler.schedule(CAMERA, DIR).len()
+        };
+        assert_eq!(schedule(4_000), 2);
+        assert_eq!(schedule(1_000), 4);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod blas_granularity;
+pub mod blas_scheduler;
+pub mod palette;
+pub mod reclaim;
+pub mod settings;
+pub mod tasks;
+pub mod timestep;
+pub mod triggers;
@@ -1,10 +1,12 @@
 use std::{
     error::Error,
     ffi::{c_char, CStr},
+    time::Duration,
 };
 
 use winit::{
-    event_loop::EventLoop,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
     raw_window_handle::{HasDisplayHandle, HasWindowHandle},
     window::Window,
     window::WindowBuilder,
@@ -12,6 +14,8 @@ use winit::{
 
 use ash::vk;
 
+use voxel::timestep::FixedTimestep;
+
 const unsafe fn cstr(a: &'static str) -> &std::ffi::CStr {
     std::ffi::CStr::from_bytes_with_nul_unchecked(a.as_bytes())
 }
@@ -29,11 +33,13 @@ const DEVICE_EXTENSION_NAMES: [*const c_char; 5] = unsafe {
 
 const WIDTH: u32 = 800;
 const HEIGHT: u32 = 600;
+const TICKS_PER_SECOND: u32 = 60;
 
 struct VoxelRenderer {
     instance: ash::Instance,
     entry: ash::Entry,
-    event_loop: EventLoop<()>,
+    // taken when the renderer starts running
+    event_loop: Option<EventLoop<()>>,
     window: Window,
     debug_callback: vk::DebugUtilsMessengerEXT,
 }
@@ -328,19 +334,58 @@ impl VoxelRenderer {
 
             Ok(Self {
                 entry,
-                event_loop,
+                event_loop: Some(event_loop),
                 window,
                 instance,
                 debug_callback,
             })
         }
     }
+    fn update(&mut self, _dt: Duration) {
+        // physics, automata & gameplay ticks go here and must only depend on
+        // dt & their own state so runs are reproducible
+    }
+    fn render(&mut self, _alpha: f32) {
+        // once there is simulation state worth drawing, alpha interpolates
+        // between the previous & current tick
+    }
+    pub fn run(mut self) -> Result<(), Box<dyn Error>> {
+        let event_loop = self
+            .event_loop
+            .take()
+            .ok_or("Renderer is already running!")?;
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+
+        event_loop.run(|event, elwt| match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => elwt.exit(),
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => self.render(timestep.alpha()),
+            // ticks run here rather than on redraw so the simulation keeps
+            // going while the window is occluded or minimised
+            Event::AboutToWait => {
+                for _ in 0..timestep.advance() {
+                    self.update(timestep.step());
+                }
+                self.window.request_redraw();
+            }
+            _ => {}
+        })?;
+
+        Ok(())
+    }
 }
 
 fn main() {
     env_logger::init();
 
-    VoxelRenderer::new(800, 600).unwrap();
+    VoxelRenderer::new(800, 600).unwrap().run().unwrap();
 }
 
 unsafe extern "system" fn vulkan_debug_callback(
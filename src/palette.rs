@@ -0,0 +1,290 @@
+use std::{error::Error, fmt::Write};
+
+// matches the 256 entry rgba palettes used by .vox files so every colour
+// can be addressed by a u8
+pub const MAX_COLOURS: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    colours: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    pub fn new(name: &str, colours: Vec<[u8; 4]>) -> Result<Self, Box<dyn Error>> {
+        if colours.len() > MAX_COLOURS {
+            return Err(format!(
+                "Palette has {} colours, at most {MAX_COLOURS} are supported!",
+                colours.len()
+            )
+            .into());
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            colours,
+        })
+    }
+    pub fn colours(&self) -> &[[u8; 4]] {
+        &self.colours
+    }
+    // index of the perceptually closest colour, ignoring alpha
+    pub fn nearest(&self, colour: [u8; 4]) -> Option<u8> {
+        self.colours
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, c)| colour_distance(**c, colour))
+            .map(|(i, _)| i as u8)
+    }
+    // for each colour in self, the closest index in target, used to move an
+    // imported model onto the world's materials
+    pub fn remap_to(&self, target: &Palette) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.colours
+            .iter()
+            .map(|&c| target.nearest(c).ok_or("Target palette is empty!".into()))
+            .collect()
+    }
+    // hue_shift is in degrees, saturation_scale multiplies saturation
+    pub fn adjust_hsv(
+        &mut self,
+        indices: impl IntoIterator<Item = u8>,
+        hue_shift: f32,
+        saturation_scale: f32,
+    ) {
+        for i in indices {
+            let Some(colour) = self.colours.get_mut(i as usize) else {
+                continue;
+            };
+            let (h, s, v) = rgb_to_hsv(*colour);
+            let h = (h + hue_shift).rem_euclid(360.0);
+            let s = (s * saturation_scale).clamp(0.0, 1.0);
+            let [r, g, b] = hsv_to_rgb(h, s, v);
+            *colour = [r, g, b, colour[3]];
+        }
+    }
+    // gimp palette, alpha is not stored
+    pub fn to_gpl(&self) -> String {
+        let mut out = format!("GIMP Palette\nName: {}\nColumns: 16\n#\n", self.name);
+        for (i, [r, g, b, _]) in self.colours.iter().enumerate() {
+            let _ = writeln!(out, "{r:3} {g:3} {b:3}\tIndex {i}");
+        }
+        out
+    }
+    pub fn from_gpl(src: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = src.lines();
+        if lines.next().map(str::trim) != Some("GIMP Palette") {
+            return Err("Missing GIMP Palette header!".into());
+        }
+
+        let mut name = String::new();
+        let mut colours = Vec::new();
+        for line in lines.map(str::trim) {
+            if let Some(n) = line.strip_prefix("Name:") {
+                name = n.trim().to_owned();
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+            let mut channels = line.split_whitespace().take(3).map(str::parse::<u8>);
+            let mut next = || -> Result<u8, Box<dyn Error>> {
+                Ok(channels.next().ok_or(format!("Invalid colour: {line}"))??)
+            };
+            colours.push([next()?, next()?, next()?, 255]);
+        }
+
+        Self::new(&name, colours)
+    }
+    // adobe swatch exchange, each colour is written as an rgb global swatch
+    pub fn to_ase(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"ASEF");
+        out.extend_from_slice(&1u16.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes());
+        out.extend_from_slice(&(self.colours.len() as u32).to_be_bytes());
+
+        for (i, [r, g, b, _]) in self.colours.iter().enumerate() {
+            let name: Vec<u16> = format!("Index {i}").encode_utf16().chain([0]).collect();
+
+            let mut block = Vec::new();
+            block.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            for c in name {
+                block.extend_from_slice(&c.to_be_bytes());
+            }
+            block.extend_from_slice(b"RGB ");
+            for c in [r, g, b] {
+                block.extend_from_slice(&(*c as f32 / 255.0).to_be_bytes());
+            }
+            // global colour
+            block.extend_from_slice(&0u16.to_be_bytes());
+
+            out.extend_from_slice(&0x0001u16.to_be_bytes());
+            out.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            out.extend_from_slice(&block);
+        }
+        out
+    }
+    // only rgb, gray and group blocks are understood, other models are skipped
+    pub fn from_ase(name: &str, src: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BeReader(src);
+        if reader.take(4)? != b"ASEF" {
+            return Err("Missing ASEF header!".into());
+        }
+        reader.take(4)?;
+        let block_count = reader.u32()?;
+
+        let mut colours = Vec::new();
+        for _ in 0..block_count {
+            let kind = reader.u16()?;
+            let len = reader.u32()? as usize;
+            let mut block = BeReader(reader.take(len)?);
+            // 0x0001 is a colour, 0xc001 & 0xc002 start and end groups
+            if kind != 0x0001 {
+                continue;
+            }
+            let name_len = block.u16()? as usize;
+            block.take(name_len * 2)?;
+            let unit = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            match block.take(4)? {
+                b"RGB " => colours.push([
+                    unit(block.f32()?),
+                    unit(block.f32()?),
+                    unit(block.f32()?),
+                    255,
+                ]),
+                b"Gray" => {
+                    let v = unit(block.f32()?);
+                    colours.push([v, v, v, 255]);
+                }
+                model => log::warn!("Skipping unsupported ASE colour model {model:?}"),
+            }
+        }
+
+        Self::new(name, colours)
+    }
+}
+
+struct BeReader<'a>(&'a [u8]);
+
+impl<'a> BeReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.0.len() < n {
+            return Err("Unexpected end of ASE data!".into());
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+    fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+    fn f32(&mut self) -> Result<f32, Box<dyn Error>> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+}
+
+// "redmean" weighted euclidean distance, cheap and close enough to a proper
+// perceptual metric for palette matching
+fn colour_distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    let rmean = (a[0] as i32 + b[0] as i32) / 2;
+    let [dr, dg, db] = [0, 1, 2].map(|i| a[i] as i32 - b[i] as i32);
+    ((((512 + rmean) * dr * dr) >> 8) + 4 * dg * dg + (((767 - rmean) * db * db) >> 8)) as u32
+}
+
+fn rgb_to_hsv([r, g, b, _]: [u8; 4]) -> (f32, f32, f32) {
+    let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    (h, s, max)
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    [r, g, b].map(|c| ((c + m) * 255.0).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> Palette {
+        Palette::new(
+            "test",
+            vec![
+                [255, 0, 0, 255],
+                [0, 128, 255, 255],
+                [10, 10, 10, 255],
+                [0, 0, 0, 255],
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn gpl_round_trip() {
+        let p = palette();
+        assert_eq!(Palette::from_gpl(&p.to_gpl()).unwrap(), p);
+    }
+
+    #[test]
+    fn ase_round_trip() {
+        let p = palette();
+        assert_eq!(Palette::from_ase("test", &p.to_ase()).unwrap(), p);
+    }
+
+    #[test]
+    fn ase_truncated() {
+        let ase = palette().to_ase();
+        assert!(Palette::from_ase("test", &ase[..ase.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized() {
+        let p = Palette {
+            name: "big".to_owned(),
+            colours: vec![[0, 0, 0, 255]; MAX_COLOURS + 1],
+        };
+        assert!(Palette::from_gpl(&p.to_gpl()).is_err());
+        assert!(Palette::from_ase("big", &p.to_ase()).is_err());
+        assert!(Palette::new("big", p.colours).is_err());
+    }
+
+    #[test]
+    fn nearest() {
+        let p = palette();
+        assert_eq!(p.nearest([200, 30, 30, 255]), Some(0));
+        assert_eq!(p.nearest([1, 1, 1, 255]), Some(3));
+        assert_eq!(Palette::new("empty", vec![]).unwrap().nearest([0; 4]), None);
+    }
+
+    #[test]
+    fn hue_shift() {
+        let mut p = palette();
+        p.adjust_hsv([0], 120.0, 1.0);
+        assert_eq!(p.colours()[0], [0, 255, 0, 255]);
+        assert_eq!(p.colours()[1], [0, 128, 255, 255]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ash::vk;
+
+pub enum Resource {
+    Buffer(vk::Buffer),
+    Memory(vk::DeviceMemory),
+    ImageView(vk::ImageView),
+    Image(vk::Image),
+    AccelerationStructure(vk::AccelerationStructureKHR),
+}
+
+// resources are retired with the timeline value of the last submission that
+// used them and only destroyed once the gpu has passed that value
+#[derive(Default)]
+pub struct ReclaimQueue {
+    // kept sorted by retire value, oldest first
+    pending: VecDeque<(u64, Resource)>,
+}
+
+impl ReclaimQueue {
+    // last use values aren't monotonic, a blas last used a few frames ago can
+    // be retired after a buffer used this frame
+    pub fn retire(&mut self, resource: Resource, last_use: u64) {
+        let index = self.pending.partition_point(|(v, _)| *v <= last_use);
+        self.pending.insert(index, (last_use, resource));
+    }
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+    /// Destroys completed resources until the budget runs out, at least one
+    /// resource is freed per call so the queue can't grow forever.
+    ///
+    /// # Safety
+    /// `completed` must be a timeline value the gpu has actually reached and
+    /// every resource must have been created from `device`.
+    pub unsafe fn process(
+        &mut self,
+        device: &ash::Device,
+        as_loader: &ash::khr::acceleration_structure::Device,
+        completed: u64,
+        budget: Duration,
+    ) -> usize {
+        let start = Instant::now();
+        let mut destroyed = 0;
+        while let Some((last_use, _)) = self.pending.front() {
+            if *last_use > completed || (destroyed > 0 && start.elapsed() >= budget) {
+                break;
+            }
+            let (_, resource) = self.pending.pop_front().unwrap();
+            Self::destroy(device, as_loader, resource);
+            destroyed += 1;
+        }
+        destroyed
+    }
+    /// # Safety
+    /// `device` must be idle and every resource must have been created from it.
+    pub unsafe fn flush(
+        &mut self,
+        device: &ash::Device,
+        as_loader: &ash::khr::acceleration_structure::Device,
+    ) {
+        for (_, resource) in self.pending.drain(..) {
+            Self::destroy(device, as_loader, resource);
+        }
+    }
+    unsafe fn destroy(
+        device: &ash::Device,
+        as_loader: &ash::khr::acceleration_structure::Device,
+        resource: Resource,
+    ) {
+        match resource {
+            Resource::Buffer(buffer) => device.destroy_buffer(buffer, None),
+            Resource::Memory(memory) => device.free_memory(memory, None),
+            Resource::ImageView(view) => device.destroy_image_view(view, None),
+            Resource::Image(image) => device.destroy_image(image, None),
+            Resource::AccelerationStructure(accel) => {
+                as_loader.destroy_acceleration_structure(accel, None)
+            }
+        }
+    }
+}
+
+impl Drop for ReclaimQueue {
+    fn drop(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        log::error!(
+            "Reclaim queue dropped with {} resources pending, call flush first!",
+            self.pending.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    fn out_of_order_retire_stays_sorted() {
+        let mut queue = ReclaimQueue::default();
+        for (raw, last_use) in [(1, 5), (2, 3), (3, 7), (4, 3), (5, 1)] {
+            queue.retire(Resource::Buffer(vk::Buffer::from_raw(raw)), last_use);
+        }
+
+        let order: Vec<_> = queue
+            .pending
+            .iter()
+            .map(|(last_use, resource)| match resource {
+                Resource::Buffer(buffer) => (*last_use, buffer.as_raw()),
+                _ => unreachable!(),
+            })
+            .collect();
+        // equal values keep retire order
+        assert_eq!(order, [(1, 5), (3, 2), (3, 4), (5, 1), (7, 3)]);
+
+        // nothing was created so there's nothing to leak
+        queue.pending.clear();
+    }
+}
@@ -0,0 +1,198 @@
+use std::error::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AntiAliasing {
+    None,
+    Taa,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugView {
+    None,
+    Normals,
+    Albedo,
+    Depth,
+    TraversalSteps,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderSettings {
+    pub render_scale: f32,
+    pub anti_aliasing: AntiAliasing,
+    pub debug_view: DebugView,
+}
+
+fn validate_render_scale(scale: f32) -> Result<(), Box<dyn Error>> {
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(format!("Invalid render scale: {scale}").into());
+    }
+    Ok(())
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            render_scale: 1.0,
+            anti_aliasing: AntiAliasing::Taa,
+            debug_view: DebugView::None,
+        }
+    }
+}
+
+// unset fields fall through to the layers below
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SettingsOverride {
+    pub render_scale: Option<f32>,
+    pub anti_aliasing: Option<AntiAliasing>,
+    pub debug_view: Option<DebugView>,
+}
+
+impl SettingsOverride {
+    fn apply(&self, settings: &mut RenderSettings) {
+        if let Some(v) = self.render_scale {
+            settings.render_scale = v;
+        }
+        if let Some(v) = self.anti_aliasing {
+            settings.anti_aliasing = v;
+        }
+        if let Some(v) = self.debug_view {
+            settings.debug_view = v;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Viewport {
+    Main,
+    Minimap,
+    Photo,
+    Eye(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OverrideId(u64);
+
+struct Layer {
+    id: OverrideId,
+    owner: &'static str,
+    // None applies to every viewport
+    viewport: Option<Viewport>,
+    settings: SettingsOverride,
+}
+
+// features push overrides instead of writing the global settings so they
+// can be removed again without clobbering each other
+#[derive(Default)]
+pub struct SettingsStack {
+    global: RenderSettings,
+    layers: Vec<Layer>,
+    next_id: u64,
+}
+
+impl SettingsStack {
+    pub fn new(global: RenderSettings) -> Result<Self, Box<dyn Error>> {
+        validate_render_scale(global.render_scale)?;
+        Ok(Self {
+            global,
+            ..Default::default()
+        })
+    }
+    pub fn global(&self) -> RenderSettings {
+        self.global
+    }
+    pub fn set_global(&mut self, global: RenderSettings) -> Result<(), Box<dyn Error>> {
+        validate_render_scale(global.render_scale)?;
+        self.global = global;
+        Ok(())
+    }
+    pub fn push(
+        &mut self,
+        owner: &'static str,
+        viewport: Option<Viewport>,
+        settings: SettingsOverride,
+    ) -> Result<OverrideId, Box<dyn Error>> {
+        if let Some(scale) = settings.render_scale {
+            validate_render_scale(scale)?;
+        }
+        let id = OverrideId(self.next_id);
+        self.next_id += 1;
+        self.layers.push(Layer {
+            id,
+            owner,
+            viewport,
+            settings,
+        });
+        Ok(id)
+    }
+    pub fn remove(&mut self, id: OverrideId) {
+        self.layers.retain(|layer| layer.id != id);
+    }
+    pub fn remove_owner(&mut self, owner: &str) {
+        self.layers.retain(|layer| layer.owner != owner);
+    }
+    // global layers apply first and viewport layers on top so a viewport
+    // override always wins, within each group later pushes win
+    pub fn resolve(&self, viewport: Viewport) -> RenderSettings {
+        let mut settings = self.global;
+        let global = self.layers.iter().filter(|layer| layer.viewport.is_none());
+        let specific = self
+            .layers
+            .iter()
+            .filter(|layer| layer.viewport == Some(viewport));
+        global
+            .chain(specific)
+            .for_each(|layer| layer.settings.apply(&mut settings));
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scale(render_scale: f32) -> SettingsOverride {
+        SettingsOverride {
+            render_scale: Some(render_scale),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn viewport_beats_later_global() {
+        let mut stack = SettingsStack::default();
+        stack
+            .push("minimap", Some(Viewport::Minimap), scale(0.5))
+            .unwrap();
+        stack
+            .push(
+                "debug",
+                None,
+                SettingsOverride {
+                    render_scale: Some(2.0),
+                    debug_view: Some(DebugView::Normals),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let minimap = stack.resolve(Viewport::Minimap);
+        assert_eq!(minimap.render_scale, 0.5);
+        assert_eq!(minimap.debug_view, DebugView::Normals);
+        assert_eq!(stack.resolve(Viewport::Main).render_scale, 2.0);
+    }
+
+    #[test]
+    fn rejects_invalid_render_scale() {
+        let mut stack = SettingsStack::default();
+        for invalid in [0.0, -1.0, f32::NAN] {
+            assert!(stack.push("test", None, scale(invalid)).is_err());
+            assert!(stack
+                .set_global(RenderSettings {
+                    render_scale: invalid,
+                    ..Default::default()
+                })
+                .is_err());
+        }
+        assert_eq!(stack.resolve(Viewport::Main), RenderSettings::default());
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BinaryHeap,
+    error::Error,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+pub type TaskResult = Result<(), Box<dyn Error + Send + Sync>>;
+type TaskFn = Box<dyn FnOnce(&TaskContext) -> TaskResult + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    // idle maintenance such as world compaction
+    Background,
+    Normal,
+    // something the user is actively waiting on such as an export
+    Interactive,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskState {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+    Failed(String),
+}
+
+// shared between the task, its handle and the executor
+pub struct TaskContext {
+    name: String,
+    priority: Priority,
+    cancelled: AtomicBool,
+    // f32 bits, 0 to 1
+    progress: AtomicU32,
+    state: Mutex<TaskState>,
+}
+
+impl TaskContext {
+    // long running tasks should check this regularly and return early
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    pub fn set_progress(&self, progress: f32) {
+        self.progress
+            .store(progress.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+    fn set_state(&self, state: TaskState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskHandle(Arc<TaskContext>);
+
+impl TaskHandle {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn status(&self) -> TaskStatus {
+        TaskStatus {
+            name: self.0.name.clone(),
+            priority: self.0.priority,
+            progress: f32::from_bits(self.0.progress.load(Ordering::Relaxed)),
+            state: self.0.state.lock().unwrap().clone(),
+        }
+    }
+}
+
+// snapshot for the progress ui
+#[derive(Clone, Debug)]
+pub struct TaskStatus {
+    pub name: String,
+    pub priority: Priority,
+    pub progress: f32,
+    pub state: TaskState,
+}
+
+struct QueuedTask {
+    priority: Priority,
+    // lower sequence runs first within the same priority
+    sequence: u64,
+    context: Arc<TaskContext>,
+    func: TaskFn,
+}
+
+impl PartialEq for QueuedTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for QueuedTask {}
+
+impl PartialOrd for QueuedTask {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedTask {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    tasks: BinaryHeap<QueuedTask>,
+    sequence: u64,
+    shutdown: bool,
+}
+
+pub struct TaskExecutor {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    workers: Vec<JoinHandle<()>>,
+    handles: Vec<TaskHandle>,
+}
+
+impl TaskExecutor {
+    // leaves a core free for the render thread
+    pub fn new() -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1))
+            .unwrap_or(1)
+            .max(1);
+        Self::with_threads(threads)
+    }
+    // with more than one thread the first never runs Background tasks, so
+    // long maintenance jobs can't hold up Interactive/Normal ones
+    pub fn with_threads(threads: usize) -> Self {
+        let queue = Arc::new((Mutex::new(Queue::default()), Condvar::new()));
+        let workers = (0..threads)
+            .map(|i| {
+                let queue = queue.clone();
+                let takes_background = threads == 1 || i != 0;
+                std::thread::Builder::new()
+                    .name(format!("task worker {i}"))
+                    .spawn(move || worker(&queue, takes_background))
+                    .unwrap()
+            })
+            .collect();
+
+        Self {
+            queue,
+            workers,
+            handles: Vec::new(),
+        }
+    }
+    pub fn spawn(
+        &mut self,
+        name: &str,
+        priority: Priority,
+        func: impl FnOnce(&TaskContext) -> TaskResult + Send + 'static,
+    ) -> TaskHandle {
+        let context = Arc::new(TaskContext {
+            name: name.to_owned(),
+            priority,
+            cancelled: AtomicBool::new(false),
+            progress: AtomicU32::new(0),
+            state: Mutex::new(TaskState::Queued),
+        });
+
+        let (lock, condvar) = &*self.queue;
+        let mut queue = lock.lock().unwrap();
+        queue.sequence += 1;
+        let sequence = queue.sequence;
+        queue.tasks.push(QueuedTask {
+            priority,
+            sequence,
+            context: context.clone(),
+            func: Box::new(func),
+        });
+        // the worker woken by notify_one might be the one that skips
+        // background tasks
+        condvar.notify_all();
+
+        let handle = TaskHandle(context);
+        self.handles.push(handle.clone());
+        handle
+    }
+    // status of every task not yet collected by clear_finished
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.handles.iter().map(TaskHandle::status).collect()
+    }
+    pub fn clear_finished(&mut self) {
+        self.handles.retain(|handle| {
+            matches!(
+                *handle.0.state.lock().unwrap(),
+                TaskState::Queued | TaskState::Running
+            )
+        });
+    }
+}
+
+impl Default for TaskExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TaskExecutor {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            handle.cancel();
+        }
+        {
+            let (lock, condvar) = &*self.queue;
+            lock.lock().unwrap().shutdown = true;
+            condvar.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker(queue: &(Mutex<Queue>, Condvar), takes_background: bool) {
+    let (lock, condvar) = queue;
+    loop {
+        let task = {
+            let mut queue = lock.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    // anything still queued would otherwise report Queued forever
+                    for task in queue.tasks.drain() {
+                        task.context.set_state(TaskState::Cancelled);
+                    }
+                    return;
+                }
+                // Background is the lowest priority so if it's on top only
+                // background tasks are queued
+                let runnable = queue
+                    .tasks
+                    .peek()
+                    .is_some_and(|task| takes_background || task.priority != Priority::Background);
+                if runnable {
+                    break queue.tasks.pop().unwrap();
+                }
+                queue = condvar.wait(queue).unwrap();
+            }
+        };
+
+        let context = task.context;
+        if context.is_cancelled() {
+            context.set_state(TaskState::Cancelled);
+            continue;
+        }
+
+        context.set_state(TaskState::Running);
+        // a panicking task must not take the worker down with it
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| (task.func)(&context)));
+        let state = match result {
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_owned());
+                log::error!("Task {} panicked: {message}", context.name);
+                TaskState::Failed(message)
+            }
+            _ if context.is_cancelled() => TaskState::Cancelled,
+            Ok(Ok(())) => {
+                context.set_progress(1.0);
+                TaskState::Finished
+            }
+            Ok(Err(e)) => {
+                log::error!("Task {} failed: {e}", context.name);
+                TaskState::Failed(e.to_string())
+            }
+        };
+        context.set_state(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for(handle: &TaskHandle, state: TaskState) {
+        let start = Instant::now();
+        while handle.status().state != state {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "{} stuck in {:?}",
+                handle.status().name,
+                handle.status().state
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn until_cancelled(context: &TaskContext) -> TaskResult {
+        while !context.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn panic_does_not_kill_worker() {
+        let mut executor = TaskExecutor::with_threads(1);
+        let panics = executor.spawn("panics", Priority::Normal, |_| panic!("oops"));
+        let after = executor.spawn("after", Priority::Normal, |_| Ok(()));
+
+        wait_for(&panics, TaskState::Failed("oops".to_owned()));
+        wait_for(&after, TaskState::Finished);
+    }
+
+    #[test]
+    fn drop_cancels_queued() {
+        let mut executor = TaskExecutor::with_threads(1);
+        let running = executor.spawn("running", Priority::Normal, until_cancelled);
+        wait_for(&running, TaskState::Running);
+        let queued = executor.spawn("queued", Priority::Normal, |_| Ok(()));
+
+        drop(executor);
+        assert_eq!(running.status().state, TaskState::Cancelled);
+        assert_eq!(queued.status().state, TaskState::Cancelled);
+    }
+
+    #[test]
+    fn background_cannot_starve_interactive() {
+        let mut executor = TaskExecutor::with_threads(2);
+        let background: Vec<_> = (0..2)
+            .map(|_| executor.spawn("background", Priority::Background, until_cancelled))
+            .collect();
+        wait_for(&background[0], TaskState::Running);
+
+        let interactive = executor.spawn("interactive", Priority::Interactive, |_| Ok(()));
+        wait_for(&interactive, TaskState::Finished);
+        assert_eq!(background[1].status().state, TaskState::Queued);
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+// if a frame takes longer than this many ticks worth of time the remaining
+// time is dropped instead of trying to catch up forever
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    last: Instant,
+    tick: u64,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u32) -> Self {
+        Self {
+            step: Duration::from_secs(1) / ticks_per_second,
+            accumulator: Duration::ZERO,
+            last: Instant::now(),
+            tick: 0,
+        }
+    }
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+    // total ticks simulated so far, simulation code should derive time from
+    // this rather than the wall clock to stay deterministic
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    // returns how many fixed ticks should be simulated this frame
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        self.accumulator += now - self.last;
+        self.last = now;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.step && ticks < MAX_TICKS_PER_FRAME {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+        if ticks == MAX_TICKS_PER_FRAME && self.accumulator >= self.step {
+            let remainder =
+                Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64);
+            log::warn!(
+                "Simulation fell behind, dropping {:?}",
+                self.accumulator - remainder
+            );
+            self.accumulator = remainder;
+        }
+
+        self.tick += ticks as u64;
+        ticks
+    }
+    // how far between the previous and current tick rendering is, used to
+    // interpolate simulation state
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+}
@@ -0,0 +1,154 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Enter,
+    Leave,
+}
+
+type Callback = Box<dyn FnMut(&str, TriggerEvent)>;
+
+// axis aligned box in world space
+pub struct TriggerVolume {
+    pub name: String,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    // disarmed after the first enter/leave pair, for one off tour/demo steps
+    pub once: bool,
+    inside: bool,
+    disarmed: bool,
+}
+
+impl TriggerVolume {
+    pub fn new(name: &str, min: [f32; 3], max: [f32; 3], once: bool) -> Self {
+        Self {
+            name: name.to_owned(),
+            min,
+            max,
+            once,
+            inside: false,
+            disarmed: false,
+        }
+    }
+    fn contains(&self, p: [f32; 3]) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+    // slab test so fast movement can't skip over thin volumes
+    fn intersects_segment(&self, from: [f32; 3], to: [f32; 3]) -> bool {
+        let (mut t_min, mut t_max) = (0.0f32, 1.0f32);
+        for i in 0..3 {
+            let d = to[i] - from[i];
+            if d == 0.0 {
+                if from[i] < self.min[i] || from[i] > self.max[i] {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (self.min[i] - from[i]) / d;
+            let t1 = (self.max[i] - from[i]) / d;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_min > t_max {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Default)]
+pub struct Triggers {
+    volumes: Vec<TriggerVolume>,
+    callbacks: Vec<Callback>,
+    last_position: Option<[f32; 3]>,
+}
+
+impl Triggers {
+    pub fn add(&mut self, volume: TriggerVolume) {
+        self.volumes.push(volume);
+    }
+    // callbacks receive every event, they filter on the volume name
+    pub fn on_event(&mut self, callback: impl FnMut(&str, TriggerEvent) + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+    // should be called once per simulation tick with the camera/player position
+    pub fn update(&mut self, position: [f32; 3]) {
+        let last = self.last_position.replace(position).unwrap_or(position);
+
+        for volume in &mut self.volumes {
+            if volume.disarmed {
+                continue;
+            }
+            let inside = volume.contains(position);
+            let events: &[TriggerEvent] = match (volume.inside, inside) {
+                (false, true) => &[TriggerEvent::Enter],
+                (true, false) => &[TriggerEvent::Leave],
+                (false, false) if volume.intersects_segment(last, position) => {
+                    &[TriggerEvent::Enter, TriggerEvent::Leave]
+                }
+                _ => continue,
+            };
+            volume.inside = inside;
+
+            for &event in events {
+                log::debug!("Trigger {} {event:?}", volume.name);
+                for callback in &mut self.callbacks {
+                    callback(&volume.name, event);
+                }
+                if event == TriggerEvent::Leave && volume.once {
+                    volume.disarmed = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn triggers(volume: TriggerVolume) -> (Triggers, Rc<RefCell<Vec<TriggerEvent>>>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut triggers = Triggers::default();
+        triggers.add(volume);
+        let log = events.clone();
+        triggers.on_event(move |_, event| log.borrow_mut().push(event));
+        (triggers, events)
+    }
+
+    fn thin(once: bool) -> TriggerVolume {
+        TriggerVolume::new("thin", [0.0, -1.0, -1.0], [0.1, 1.0, 1.0], once)
+    }
+
+    #[test]
+    fn fast_segment_through_thin_volume() {
+        let (mut triggers, events) = triggers(thin(false));
+        triggers.update([-5.0, 0.0, 0.0]);
+        triggers.update([5.0, 0.0, 0.0]);
+        assert_eq!(*events.borrow(), [TriggerEvent::Enter, TriggerEvent::Leave]);
+    }
+
+    #[test]
+    fn once_disarms_after_leave() {
+        let (mut triggers, events) = triggers(thin(true));
+        triggers.update([-5.0, 0.0, 0.0]);
+        triggers.update([0.05, 0.0, 0.0]);
+        assert_eq!(*events.borrow(), [TriggerEvent::Enter]);
+
+        triggers.update([5.0, 0.0, 0.0]);
+        assert_eq!(*events.borrow(), [TriggerEvent::Enter, TriggerEvent::Leave]);
+
+        triggers.update([-5.0, 0.0, 0.0]);
+        triggers.update([0.05, 0.0, 0.0]);
+        assert_eq!(events.borrow().len(), 2);
+    }
+
+    // there's no previous position so no segment, even one from the origin
+    // that would cross the volume
+    #[test]
+    fn first_update_fires_nothing() {
+        let volume = TriggerVolume::new("thin", [2.0, -1.0, -1.0], [2.1, 1.0, 1.0], false);
+        let (mut triggers, events) = triggers(volume);
+        triggers.update([5.0, 0.0, 0.0]);
+        assert!(events.borrow().is_empty());
+    }
+}